@@ -4,8 +4,11 @@ use winit::{
     application::ApplicationHandler, dpi::PhysicalSize, event::{ElementState, KeyEvent, WindowEvent}, event_loop::ActiveEventLoop, keyboard::{KeyCode, PhysicalKey}, window::{Window, WindowId}
 };
 use log::{error, info};
+use wgpu::PushConstantRange;
 
 use crate::imgui_winit_support;
+use crate::common::util::{create_shader_module, include_shaders};
+use crate::graph::{Pass, Resources};
 
 pub trait App {
     async fn new(window: Arc<Window>) -> Self;
@@ -97,9 +100,17 @@ impl WGPUContext {
             .await
             .expect("Failed to find an appropriate adapter");
 
+        // Intersect with what the adapter actually supports so timestamp queries degrade
+        // gracefully to `None` in `Pathtracer::new` instead of panicking here.
+        let required_features = (wgpu::Features::TIMESTAMP_QUERY | wgpu::Features::TIMESTAMP_QUERY_INSIDE_PASSES)
+            & adapter.features();
+
         let (device, queue) = adapter
             .request_device(
-                &wgpu::DeviceDescriptor::default(),
+                &wgpu::DeviceDescriptor {
+                    required_features,
+                    ..Default::default()
+                },
                 None
             )
             .await
@@ -112,7 +123,7 @@ impl WGPUContext {
 
         let config = wgpu::SurfaceConfiguration {
             usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-            format: wgpu::TextureFormat::Bgra8UnormSrgb, // TODO: Use Rgba16Float, but it's not supported with imgui-wgpu
+            format: wgpu::TextureFormat::Bgra8UnormSrgb, // imgui-wgpu can't render to Rgba16Float; HDR accumulation now lives entirely in the pathtracer's `"pathtracer_output"` texture and reaches this format only through `DisplayPass`'s tonemap+OETF blit
             width: size.width,
             height: size.height,
             present_mode: wgpu::PresentMode::AutoNoVsync,
@@ -140,6 +151,43 @@ impl WGPUContext {
     }
 }
 
+/// `WGPUContext` without a surface, for batch-rendering a scene to a file without opening a window.
+pub struct HeadlessContext {
+    pub device: wgpu::Device,
+    pub queue: wgpu::Queue,
+}
+
+impl HeadlessContext {
+    pub async fn new() -> Self {
+        let instance = wgpu::Instance::default();
+
+        let adapter = instance
+            .request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: wgpu::PowerPreference::default(),
+                force_fallback_adapter: false,
+                compatible_surface: None,
+            })
+            .await
+            .expect("Failed to find an appropriate adapter");
+
+        let required_features = (wgpu::Features::TIMESTAMP_QUERY | wgpu::Features::TIMESTAMP_QUERY_INSIDE_PASSES)
+            & adapter.features();
+
+        let (device, queue) = adapter
+            .request_device(
+                &wgpu::DeviceDescriptor {
+                    required_features,
+                    ..Default::default()
+                },
+                None
+            )
+            .await
+            .expect("Failed to create device");
+
+        Self { device, queue }
+    }
+}
+
 pub struct ImGuiContext {
     pub renderer: imgui_wgpu::Renderer,
     pub ctx: imgui::Context,
@@ -220,6 +268,13 @@ pub struct PerformanceMetrics<const BUFFER_SIZE: usize> {
     idx: usize,
     n_frames: usize,
     summed_frame_time: std::time::Duration,
+    // Parallel ring buffer of GPU pass times, fed by whoever resolves the timestamp queries (e.g.
+    // the caller of `Pathtracer::read_gpu_time`) since CPU frame pacing and GPU readback land at different times.
+    curr_gpu_time: std::time::Duration,
+    gpu_time_buffer: [std::time::Duration; BUFFER_SIZE],
+    gpu_idx: usize,
+    n_gpu_samples: usize,
+    summed_gpu_time: std::time::Duration,
 }
 
 impl<const BUFFER_SIZE: usize> Default for PerformanceMetrics<BUFFER_SIZE>{
@@ -232,6 +287,11 @@ impl<const BUFFER_SIZE: usize> Default for PerformanceMetrics<BUFFER_SIZE>{
             idx: 0,
             n_frames: 0,
             summed_frame_time: std::time::Duration::default(),
+            curr_gpu_time: std::time::Duration::default(),
+            gpu_time_buffer: [std::time::Duration::default(); BUFFER_SIZE],
+            gpu_idx: 0,
+            n_gpu_samples: 0,
+            summed_gpu_time: std::time::Duration::default(),
         }
     }
 }
@@ -287,4 +347,203 @@ impl<const BUFFER_SIZE: usize> PerformanceMetrics<BUFFER_SIZE> {
     pub fn curr_frame_rate(&self) -> f32 {
         1.0 / self.curr_frame_time.as_secs_f32()
     }
+
+    /// Feeds one resolved GPU pass duration into the parallel ring buffer.
+    pub fn record_gpu_time(&mut self, gpu_time: std::time::Duration) {
+        self.curr_gpu_time = gpu_time;
+
+        self.summed_gpu_time += gpu_time;
+        if self.n_gpu_samples < BUFFER_SIZE {
+            self.n_gpu_samples += 1;
+        } else {
+            self.summed_gpu_time -= self.gpu_time_buffer[self.gpu_idx];
+        }
+
+        self.gpu_time_buffer[self.gpu_idx] = gpu_time;
+        self.gpu_idx = (self.gpu_idx + 1) % BUFFER_SIZE;
+    }
+
+    pub fn avg_gpu_time(&self) -> std::time::Duration {
+        self.summed_gpu_time.checked_div(self.n_gpu_samples as u32).unwrap_or_default()
+    }
+
+    pub fn curr_gpu_time(&self) -> std::time::Duration {
+        self.curr_gpu_time
+    }
+}
+
+/// Tonemapping operator applied by [`DisplayPass`] before the sRGB OETF.
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TonemapOperator {
+    Reinhard = 0,
+    Aces = 1,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, bytemuck::NoUninit)]
+struct TonemapPushConstants {
+    exposure: f32,
+    operator: u32,
+}
+
+/// Final fullscreen pass that reads the pathtracer's HDR `Rgba32Float` output, applies exposure
+/// and a tonemapping operator, and writes the tonemapped+sRGB-encoded result into the swapchain.
+///
+/// This decouples the pathtracer's accumulation buffer from the surface format, which is forced
+/// to `Bgra8UnormSrgb` in [`WGPUContext::new`] because `imgui-wgpu` can't render to `Rgba16Float`.
+pub struct DisplayPass {
+    pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    bind_group: wgpu::BindGroup,
+    sampler: wgpu::Sampler,
+    pub exposure: f32,
+    pub operator: TonemapOperator,
+}
+
+impl DisplayPass {
+    pub fn new(wgpu: &WGPUContext, output: &Texture) -> Self {
+        let module = create_shader_module!(wgpu.device, "Tonemap", "tonemap.wgsl");
+
+        let bind_group_layout = wgpu.device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Tonemap Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    // `output` is Rgba32Float, which is unfilterable-float unless the device
+                    // requests `Features::FLOAT32_FILTERABLE` (it doesn't) - match the `Nearest`
+                    // sampler below rather than declaring a filterable binding it can't satisfy.
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::NonFiltering),
+                    count: None,
+                },
+            ],
+        });
+
+        let sampler = wgpu.device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Tonemap Sampler"),
+            mag_filter: wgpu::FilterMode::Nearest,
+            min_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+
+        let bind_group = Self::create_bind_group(wgpu, &bind_group_layout, output, &sampler);
+
+        let layout = wgpu.device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Tonemap Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[PushConstantRange {
+                stages: wgpu::ShaderStages::FRAGMENT,
+                range: 0..std::mem::size_of::<TonemapPushConstants>() as u32,
+            }],
+        });
+
+        let pipeline = wgpu.device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Tonemap Pipeline"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &module,
+                entry_point: "vs_main",
+                buffers: &[],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &module,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu.config.format.into())],
+                compilation_options: Default::default(),
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        Self {
+            pipeline,
+            bind_group_layout,
+            bind_group,
+            sampler,
+            exposure: 1.0,
+            operator: TonemapOperator::Reinhard,
+        }
+    }
+
+    fn create_bind_group(wgpu: &WGPUContext, layout: &wgpu::BindGroupLayout, output: &Texture, sampler: &wgpu::Sampler) -> wgpu::BindGroup {
+        wgpu.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Tonemap Bind Group"),
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(output.view()),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(sampler),
+                },
+            ],
+        })
+    }
+
+    /// Re-binds the pathtracer's output texture, e.g. after a resize or resolution change.
+    pub fn update(&mut self, wgpu: &WGPUContext, output: &Texture) {
+        self.bind_group = Self::create_bind_group(wgpu, &self.bind_group_layout, output, &self.sampler);
+    }
+
+    /// Tonemaps `output` into `view` (the current swapchain texture view).
+    pub fn render(&self, encoder: &mut wgpu::CommandEncoder, view: &wgpu::TextureView) {
+        let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Tonemap Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        rpass.set_pipeline(&self.pipeline);
+        rpass.set_bind_group(0, &self.bind_group, &[]);
+        let pc = TonemapPushConstants { exposure: self.exposure, operator: self.operator as u32 };
+        rpass.set_push_constants(wgpu::ShaderStages::FRAGMENT, 0, bytemuck::cast_slice(&[pc]));
+        rpass.draw(0..3, 0..1);
+    }
+
+    /// Draws the exposure/operator controls into the existing imgui overlay.
+    pub fn ui(&mut self, ui: &imgui::Ui) {
+        ui.slider("Exposure", 0.01, 16.0, &mut self.exposure);
+        let mut operator_idx = self.operator as usize;
+        if ui.combo_simple_string("Tonemap Operator", &mut operator_idx, &["Reinhard", "ACES"]) {
+            self.operator = if operator_idx == 1 { TonemapOperator::Aces } else { TonemapOperator::Reinhard };
+        }
+    }
+}
+
+/// Reads the pathtracer's output from the `"pathtracer_output"` resource registered by
+/// `Pathtracer::new`, and blits into the `"swapchain"` texture view inserted fresh every frame by
+/// whoever acquires the surface texture.
+impl Pass for DisplayPass {
+    fn record(&self, encoder: &mut wgpu::CommandEncoder, resources: &Resources) {
+        self.render(encoder, resources.texture_view("swapchain"));
+    }
+
+    fn resize(&mut self, wgpu: &WGPUContext, resources: &Resources) {
+        self.update(wgpu, resources.texture("pathtracer_output"));
+    }
 }
\ No newline at end of file
@@ -0,0 +1,133 @@
+use std::collections::HashMap;
+
+use glam::{uvec2, UVec2};
+
+use crate::common::{Texture, WGPUContext};
+
+/// A single recorded step of a [`Graph`]: pathtracing, denoising, tonemapping, etc.
+///
+/// `record` only encodes commands, it never allocates resources itself - that's centralized in
+/// [`Resources`] so passes can be chained without each one re-implementing resize plumbing.
+pub trait Pass {
+    fn record(&self, encoder: &mut wgpu::CommandEncoder, resources: &Resources);
+
+    /// Called whenever the graph's resources are (re)allocated, e.g. after a window resize.
+    /// Most passes only read resources someone else owns and can keep the default no-op.
+    fn resize(&mut self, _wgpu: &WGPUContext, _resources: &Resources) {}
+}
+
+/// A compute pipeline bundled with the layout it was built from, so passes can keep both without
+/// tracking them separately.
+pub struct ComputePipeline {
+    pub layout: wgpu::PipelineLayout,
+    pub pipeline: wgpu::ComputePipeline,
+}
+
+/// A render pipeline bundled with the layout it was built from.
+pub struct RenderPipeline {
+    pub layout: wgpu::PipelineLayout,
+    pub pipeline: wgpu::RenderPipeline,
+}
+
+/// Named intermediate textures, buffers and bind groups shared between passes in a [`Graph`].
+///
+/// Textures are registered with a factory closure so the registry can recreate all of them
+/// centrally on resize, instead of every pass re-deriving the current surface size itself.
+/// Factories only take a `&wgpu::Device` + explicit size rather than a `&WGPUContext`, so
+/// resources can be allocated from a surface-less `HeadlessContext` too.
+#[derive(Default)]
+pub struct Resources {
+    textures: HashMap<&'static str, Texture>,
+    texture_factories: HashMap<&'static str, Box<dyn Fn(&wgpu::Device, UVec2) -> Texture>>,
+    buffers: HashMap<&'static str, wgpu::Buffer>,
+    bind_groups: HashMap<&'static str, wgpu::BindGroup>,
+    // Not resized by `Resources::resize` - the swapchain view is re-inserted every frame by
+    // whoever owns the surface, since it doesn't exist until the frame's texture is acquired.
+    texture_views: HashMap<&'static str, wgpu::TextureView>,
+}
+
+impl Resources {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allocates `name` via `factory` at `size` and remembers the factory so future resizes can
+    /// recreate it at whatever size is current then.
+    pub fn register_texture(&mut self, device: &wgpu::Device, name: &'static str, size: UVec2, factory: impl Fn(&wgpu::Device, UVec2) -> Texture + 'static) {
+        let texture = factory(device, size);
+        self.textures.insert(name, texture);
+        self.texture_factories.insert(name, Box::new(factory));
+    }
+
+    pub fn texture(&self, name: &str) -> &Texture {
+        self.textures.get(name).unwrap_or_else(|| panic!("Unknown resource texture `{name}`"))
+    }
+
+    pub fn insert_buffer(&mut self, name: &'static str, buffer: wgpu::Buffer) {
+        self.buffers.insert(name, buffer);
+    }
+
+    pub fn buffer(&self, name: &str) -> &wgpu::Buffer {
+        self.buffers.get(name).unwrap_or_else(|| panic!("Unknown resource buffer `{name}`"))
+    }
+
+    pub fn insert_bind_group(&mut self, name: &'static str, bind_group: wgpu::BindGroup) {
+        self.bind_groups.insert(name, bind_group);
+    }
+
+    pub fn bind_group(&self, name: &str) -> &wgpu::BindGroup {
+        self.bind_groups.get(name).unwrap_or_else(|| panic!("Unknown resource bind group `{name}`"))
+    }
+
+    /// Inserts a per-frame texture view, e.g. the swapchain view a final pass renders into.
+    /// Unlike `textures`, these aren't owned by a factory and must be re-inserted every frame.
+    pub fn insert_texture_view(&mut self, name: &'static str, view: wgpu::TextureView) {
+        self.texture_views.insert(name, view);
+    }
+
+    pub fn texture_view(&self, name: &str) -> &wgpu::TextureView {
+        self.texture_views.get(name).unwrap_or_else(|| panic!("Unknown resource texture view `{name}`"))
+    }
+
+    /// Recreates every registered texture at `size`.
+    pub fn resize(&mut self, device: &wgpu::Device, size: UVec2) {
+        for (name, factory) in &self.texture_factories {
+            self.textures.insert(name, factory(device, size));
+        }
+    }
+}
+
+/// An ordered list of [`Pass`]es sharing a central [`Resources`] registry.
+///
+/// Resizing and resource allocation happen once on the graph instead of being re-implemented by
+/// every pass, so inserting e.g. a denoiser or the tonemap pass between existing stages doesn't
+/// require touching their resize logic.
+#[derive(Default)]
+pub struct Graph {
+    passes: Vec<Box<dyn Pass>>,
+    pub resources: Resources,
+}
+
+impl Graph {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_pass(&mut self, pass: Box<dyn Pass>) {
+        self.passes.push(pass);
+    }
+
+    pub fn resize(&mut self, wgpu: &WGPUContext) {
+        let size = uvec2(wgpu.config.width, wgpu.config.height);
+        self.resources.resize(&wgpu.device, size);
+        for pass in &mut self.passes {
+            pass.resize(wgpu, &self.resources);
+        }
+    }
+
+    pub fn record(&self, encoder: &mut wgpu::CommandEncoder) {
+        for pass in &self.passes {
+            pass.record(encoder, &self.resources);
+        }
+    }
+}
@@ -1,7 +1,8 @@
+use std::cell::Cell;
 use std::collections::HashMap;
 use std::time::Instant;
 
-use glam::{uvec2, Vec3Swizzles, Vec4};
+use glam::{UVec2, Vec3Swizzles, Vec4};
 use itertools::iproduct;
 use sobol_burley::sample_4d;
 use wgpu::util::DeviceExt;
@@ -9,18 +10,73 @@ use wgpu::PushConstantRange;
 
 use crate::common::util::{create_shader_module, include_shaders};
 use crate::common::{CameraController, Texture, WGPUContext};
+use crate::graph::{Pass, Resources};
 use super::envmap::EnvMap;
 use super::scene::SceneBuffers;
 
+/// Name of the `Resources` texture this pass writes into. Registered by `Pathtracer::new` so
+/// `DisplayPass` (and anything else in the `Graph`) can read the same texture by name instead of
+/// each pass allocating its own copy.
+const OUTPUT_TEXTURE: &str = "pathtracer_output";
+/// Name of the `Resources` bind group `Pass::record` reads the scene through.
+const SCENE_BIND_GROUP: &str = "scene";
+
 pub struct Pathtracer {
     pipeline: wgpu::ComputePipeline,
     global_layout: wgpu::BindGroupLayout,
     global_group: wgpu::BindGroup,
-    output: Texture,
     lds_buffer: wgpu::Buffer,
-    pub globals: Globals,
+    // Interior mutability lets `record` advance the sample count through `&self`, as required by
+    // the `Pass` trait - `Graph::record` only ever holds a shared borrow of its passes.
+    globals: Cell<Globals>,
+    // Set by `Pass::resize` once `Resources` reallocates `OUTPUT_TEXTURE`: `global_group` still
+    // points at the old texture's view until `Self::update` rebuilds it with the current
+    // camera/envmap, which `Pass::resize` alone can't do (it doesn't have access to either). Checked
+    // in `record_dispatch` so a missed `update` call is a hard panic, not a silently stale render.
+    bind_group_dirty: Cell<bool>,
     pub resolution_factor: f32,
-    pub max_sample_count: u32,
+    // Depth of the precomputed LDS buffer, in samples per pixel-dimension slot. Accumulation is
+    // unbounded - once `globals.sample` exceeds this, `fetch_sample` in `pathtracing.wgsl` wraps
+    // the buffer index via `lds_index` instead of the renderer stopping. Fixed at construction
+    // (mirrored into `globals.buffered_samples`, see `Globals`) since `lds_buffer` itself is sized
+    // from it and never reallocated - not `pub` so nothing can desync the two after the fact.
+    max_sample_count: u32,
+    // `None` when the adapter doesn't support `Features::TIMESTAMP_QUERY_INSIDE_PASSES`, which is
+    // what `ComputePassTimestampWrites` actually requires (plain `TIMESTAMP_QUERY` only covers
+    // encoder-level `write_timestamp`, not writes scoped to a compute pass).
+    timestamps: Option<GpuTimestamps>,
+}
+
+/// Resources for timing the compute pass on the GPU: a begin/end timestamp pair, resolved into a
+/// `QUERY_RESOLVE` buffer and copied into a `MAP_READ` buffer for CPU readback.
+struct GpuTimestamps {
+    query_set: wgpu::QuerySet,
+    resolve_buffer: wgpu::Buffer,
+    readback_buffer: wgpu::Buffer,
+}
+
+impl GpuTimestamps {
+    fn new(device: &wgpu::Device) -> Self {
+        let query_set = device.create_query_set(&wgpu::QuerySetDescriptor {
+            label: Some("Raytracer Timestamps"),
+            ty: wgpu::QueryType::Timestamp,
+            count: 2,
+        });
+        let size = 2 * std::mem::size_of::<u64>() as u64;
+        let resolve_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Raytracer Timestamp Resolve Buffer"),
+            size,
+            usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let readback_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Raytracer Timestamp Readback Buffer"),
+            size,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        Self { query_set, resolve_buffer, readback_buffer }
+    }
 }
 
 #[repr(C)]
@@ -30,15 +86,23 @@ pub struct Globals {
     weight: f32,
     pub bounces: u32,
     pub contribution_factor: f32,
+    // 0/1 rather than bool so the struct stays `bytemuck::NoUninit` for the push constant block.
+    pub use_correlated_sampling: u32,
+    // Mirrors `Pathtracer::max_sample_count` - `pathtracing.wgsl`'s `fetch_sample` needs the real
+    // LDS buffer depth to wrap `lds_index` correctly, and a hardcoded shader-side constant would
+    // silently desync the moment `max_sample_count` changes.
+    buffered_samples: u32,
 }
 
-impl Default for Globals {
-    fn default() -> Self {
-        Self { 
+impl Globals {
+    fn new(max_sample_count: u32) -> Self {
+        Self {
             sample: 0,
             weight: 0.0,
             bounces: 8,
             contribution_factor: 4.0,
+            use_correlated_sampling: 0,
+            buffered_samples: max_sample_count,
         }
     }
 }
@@ -48,12 +112,16 @@ impl Pathtracer {
     const COMPUTE_SIZE: u32 = 8;
     const LDS_PER_BOUNCE: u32 = 2;
 
-    pub fn new(wgpu: &WGPUContext, scene: &SceneBuffers, camera: &CameraController, envmap: &EnvMap) -> Self {
+    /// `output_size` is the base resolution `resolution_factor` scales from - the window's inner
+    /// size for a live `WGPUContext`, or any requested resolution for a headless render, since
+    /// this only needs a `&wgpu::Device` and never reads a `WGPUContext`'s surface config.
+    pub fn new(device: &wgpu::Device, output_size: UVec2, resources: &mut Resources, scene: &SceneBuffers, camera: &CameraController, envmap: &EnvMap) -> Self {
         let resolution_factor = 0.3;
-        let output = Self::create_output_texture(wgpu, resolution_factor);
+        resources.register_texture(device, OUTPUT_TEXTURE, output_size, move |device, size| Self::create_output_texture(device, size, resolution_factor));
+        resources.insert_bind_group(SCENE_BIND_GROUP, scene.bind_group().clone());
 
-        let globals = Globals::default();
         let max_sample_count = 1024;
+        let globals = Globals::new(max_sample_count);
         let dims = globals.bounces * Self::LDS_PER_BOUNCE + 1;
         let n = max_sample_count;
 
@@ -64,13 +132,13 @@ impl Pathtracer {
         }).collect();
         log::info!("Generated Sobol-Burley-Sequence in {:?} using {} KiB", timer.elapsed(), n * dims * 32 / 1024);
 
-        let lds_buffer = wgpu.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        let lds_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: Some("Pathtracer LDS"),
             contents: bytemuck::cast_slice(&lds),
             usage: wgpu::BufferUsages::STORAGE,
         });
 
-        let global_layout = wgpu.device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        let global_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
             label: Some("Raytracer Output Layout"),
             entries: &[
                 wgpu::BindGroupLayoutEntry {
@@ -122,9 +190,9 @@ impl Pathtracer {
             ]
         });
 
-        let global_group = Self::create_global_group(wgpu, &global_layout, &output, camera, &lds_buffer, envmap);
+        let global_group = Self::create_global_group(device, &global_layout, resources.texture(OUTPUT_TEXTURE), camera, &lds_buffer, envmap);
 
-        let layout = wgpu.device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
             label: Some("Raytracer Pipeline Layout"),
             bind_group_layouts: &[&global_layout, scene.layout()],
             push_constant_ranges: &[PushConstantRange {
@@ -133,9 +201,9 @@ impl Pathtracer {
             }],
         });
 
-        let module = create_shader_module!(wgpu.device, "Pathtracer", "pathtracing.wgsl", "raytracing_sw.wgsl", "common.wgsl");
+        let module = create_shader_module!(device, "Pathtracer", "pathtracing.wgsl", "raytracing_sw.wgsl", "common.wgsl", "sampling.wgsl");
 
-        let pipeline = wgpu.device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
             label: Some("Raytracer Compute"),
             layout: Some(&layout),
             module: &module,
@@ -148,20 +216,24 @@ impl Pathtracer {
             cache: None,
         });
 
-        Self { 
+        let timestamps = device.features().contains(wgpu::Features::TIMESTAMP_QUERY_INSIDE_PASSES)
+            .then(|| GpuTimestamps::new(device));
+
+        Self {
             pipeline,
             global_layout,
             global_group,
             lds_buffer,
-            output,
-            globals,
+            globals: Cell::new(globals),
+            bind_group_dirty: Cell::new(false),
             resolution_factor,
             max_sample_count,
+            timestamps,
         }
     }
 
-    fn create_global_group(wgpu: &WGPUContext, global_layout: &wgpu::BindGroupLayout, output: &Texture, camera: &CameraController, lds_buffer: &wgpu::Buffer, envmap: &EnvMap) -> wgpu::BindGroup {
-        wgpu.device.create_bind_group(&wgpu::BindGroupDescriptor {
+    fn create_global_group(device: &wgpu::Device, global_layout: &wgpu::BindGroupLayout, output: &Texture, camera: &CameraController, lds_buffer: &wgpu::Buffer, envmap: &EnvMap) -> wgpu::BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
             label: Some("Raytracer Output Bind Group"),
             layout: global_layout,
             entries: &[
@@ -189,8 +261,8 @@ impl Pathtracer {
         })
     }
 
-    fn create_output_texture(wgpu: &WGPUContext, resolution_factor: f32) -> Texture {
-        let dim = uvec2(wgpu.config.width, wgpu.config.height).as_vec2() * resolution_factor;
+    fn create_output_texture(device: &wgpu::Device, base_size: UVec2, resolution_factor: f32) -> Texture {
+        let dim = base_size.as_vec2() * resolution_factor;
         let dim = dim.as_uvec2() / Self::COMPUTE_SIZE * Self::COMPUTE_SIZE;
 
         let size = wgpu::Extent3d {
@@ -198,43 +270,253 @@ impl Pathtracer {
             height: dim.y,
             depth_or_array_layers: 1,
         };
-        Texture::create_texture(wgpu, size, wgpu::TextureFormat::Rgba32Float)
+        Texture::create_texture(device, size, wgpu::TextureFormat::Rgba32Float)
     }
 
-    pub fn output_texture(&self) -> &Texture {
-        &self.output
+    pub fn output_texture<'r>(&self, resources: &'r Resources) -> &'r Texture {
+        resources.texture(OUTPUT_TEXTURE)
     }
 
-    pub fn resize(&mut self, wgpu: &WGPUContext) {
-        self.output = Self::create_output_texture(wgpu, self.resolution_factor);
+    /// Rebuilds `global_group` against `resources`' current output texture, e.g. after the camera
+    /// moved, the envmap was reloaded, or `resources.resize` ran and reallocated the texture. Takes
+    /// a plain `device` rather than `&WGPUContext` so it also works against a headless render,
+    /// which has no surface to speak of. Resizing the texture itself is `Resources`' job - see
+    /// `register_texture` in `Self::new`.
+    pub fn update(&mut self, device: &wgpu::Device, resources: &Resources, camera: &CameraController, envmap: &EnvMap) {
+        self.global_group = Self::create_global_group(device, &self.global_layout, resources.texture(OUTPUT_TEXTURE), camera, &self.lds_buffer, envmap);
+        self.bind_group_dirty.set(false);
+        self.invalidate();
     }
 
-    pub fn update(&mut self, wgpu: &WGPUContext, camera: &CameraController, envmap: &EnvMap) {
-        self.global_group = Self::create_global_group(wgpu, &self.global_layout, &self.output, camera, &self.lds_buffer, envmap);
-        self.invalidate();
+    pub fn bounces(&self) -> u32 {
+        self.globals.get().bounces
+    }
+
+    pub fn set_bounces(&mut self, bounces: u32) {
+        let mut globals = self.globals.get();
+        globals.bounces = bounces;
+        self.globals.set(globals);
+    }
+
+    pub fn contribution_factor(&self) -> f32 {
+        self.globals.get().contribution_factor
+    }
+
+    pub fn set_contribution_factor(&mut self, contribution_factor: f32) {
+        let mut globals = self.globals.get();
+        globals.contribution_factor = contribution_factor;
+        self.globals.set(globals);
+    }
+
+    /// `true` selects the old mode where every pixel reads the same Sobol sequence (visible
+    /// structured noise); kept around for comparison against the per-pixel decorrelated default.
+    pub fn use_correlated_sampling(&self) -> bool {
+        self.globals.get().use_correlated_sampling != 0
+    }
+
+    pub fn set_use_correlated_sampling(&mut self, use_correlated_sampling: bool) {
+        let mut globals = self.globals.get();
+        globals.use_correlated_sampling = use_correlated_sampling as u32;
+        self.globals.set(globals);
+    }
+
+    /// Draws the bounces/contribution/sampling-mode controls into the existing imgui overlay.
+    pub fn ui(&mut self, ui: &imgui::Ui) {
+        let mut bounces = self.bounces();
+        if ui.slider("Bounces", 1, 16, &mut bounces) {
+            self.set_bounces(bounces);
+        }
+        let mut contribution_factor = self.contribution_factor();
+        if ui.slider("Contribution Factor", 0.1, 16.0, &mut contribution_factor) {
+            self.set_contribution_factor(contribution_factor);
+        }
+        let mut use_correlated_sampling = self.use_correlated_sampling();
+        if ui.checkbox("Correlated Sampling (debug)", &mut use_correlated_sampling) {
+            self.set_use_correlated_sampling(use_correlated_sampling);
+        }
     }
 
     pub fn sample_count(&self) -> u32 {
-        self.globals.sample
+        self.globals.get().sample
     }
 
-    pub fn invalidate(&mut self) {
-        self.globals.sample = 0;
+    pub fn invalidate(&self) {
+        let mut globals = self.globals.get();
+        globals.sample = 0;
+        self.globals.set(globals);
     }
 
-    pub fn dispatch(&mut self, encoder: &mut wgpu::CommandEncoder, scene: &SceneBuffers) {
-        if self.globals.sample >= self.max_sample_count { return; }
+    /// Records the compute pass against `scene_bind_group`/`output`, advancing the sample count
+    /// by one. Shared by the standalone [`Self::dispatch`] entry point and the [`Pass`] impl
+    /// below, both of which source these from a [`Resources`] registry.
+    fn record_dispatch(&self, encoder: &mut wgpu::CommandEncoder, scene_bind_group: &wgpu::BindGroup, output: &Texture) {
+        assert!(!self.bind_group_dirty.get(), "Pathtracer::update wasn't called after the last resize - global_group still points at a freed output texture");
+        let mut globals = self.globals.get();
+        let timestamp_writes = self.timestamps.as_ref().map(|t| wgpu::ComputePassTimestampWrites {
+            query_set: &t.query_set,
+            beginning_of_pass_write_index: Some(0),
+            end_of_pass_write_index: Some(1),
+        });
         let mut cpass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
             label: Some("Raytracer Compute Pass"),
-            timestamp_writes: None,
+            timestamp_writes,
         });
         cpass.set_pipeline(&self.pipeline);
         cpass.set_bind_group(0, &self.global_group, &[]);
-        cpass.set_bind_group(1, scene.bind_group(), &[]);
-        self.globals.sample += 1;
-        self.globals.weight = 1.0 / self.globals.sample as f32;
-        cpass.set_push_constants(0, bytemuck::cast_slice(&[self.globals]));
-        let n_workgroups = self.output.size().xy() / Self::COMPUTE_SIZE;
+        cpass.set_bind_group(1, scene_bind_group, &[]);
+        globals.sample += 1;
+        globals.weight = 1.0 / globals.sample as f32;
+        cpass.set_push_constants(0, bytemuck::cast_slice(&[globals]));
+        let n_workgroups = output.size().xy() / Self::COMPUTE_SIZE;
         cpass.dispatch_workgroups(n_workgroups.x, n_workgroups.y, 1);
+        drop(cpass);
+        self.globals.set(globals);
+
+        if let Some(t) = &self.timestamps {
+            encoder.resolve_query_set(&t.query_set, 0..2, &t.resolve_buffer, 0);
+            encoder.copy_buffer_to_buffer(&t.resolve_buffer, 0, &t.readback_buffer, 0, t.readback_buffer.size());
+        }
+    }
+
+    pub fn dispatch(&self, encoder: &mut wgpu::CommandEncoder, resources: &Resources) {
+        self.record_dispatch(encoder, resources.bind_group(SCENE_BIND_GROUP), resources.texture(OUTPUT_TEXTURE));
+    }
+
+    /// Maps back the last resolved begin/end timestamps and converts them to a duration via
+    /// `Queue::get_timestamp_period`. Blocks on `device.poll`, so call it once the encoder that
+    /// recorded the pass has already been submitted (typically at the start of the next frame),
+    /// not immediately after `dispatch`. Returns `None` without `Features::TIMESTAMP_QUERY_INSIDE_PASSES`.
+    pub fn read_gpu_time(&self, device: &wgpu::Device, queue: &wgpu::Queue) -> Option<std::time::Duration> {
+        let timestamps = self.timestamps.as_ref()?;
+
+        let slice = timestamps.readback_buffer.slice(..);
+        let (tx, rx) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = tx.send(result);
+        });
+        device.poll(wgpu::PollType::Wait).expect("Failed to poll device");
+        rx.recv().expect("Timestamp readback mapping callback never fired").ok()?;
+
+        let ticks: [u64; 2] = {
+            let mapped = slice.get_mapped_range();
+            let ticks: &[u64] = bytemuck::cast_slice(&mapped);
+            [ticks[0], ticks[1]]
+        };
+        timestamps.readback_buffer.unmap();
+
+        let period_ns = queue.get_timestamp_period() as f64;
+        Some(std::time::Duration::from_nanos((ticks[1].saturating_sub(ticks[0]) as f64 * period_ns) as u64))
+    }
+
+    /// Dispatches until `globals.sample` reaches `sample_count`, then reads the HDR output back
+    /// and writes it to `path`. The extension picks the format: `.exr` for the raw linear buffer,
+    /// anything else for a tonemapped+sRGB PNG. Meant to be driven from a CLI against a
+    /// `HeadlessContext`, but takes a plain `device`/`queue` pair so it also works against a
+    /// windowed `WGPUContext`.
+    pub fn render_to_file(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, resources: &Resources, sample_count: u32, path: &std::path::Path) {
+        while self.sample_count() < sample_count {
+            let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Headless Render Encoder"),
+            });
+            self.dispatch(&mut encoder, resources);
+            queue.submit(Some(encoder.finish()));
+        }
+
+        let output = resources.texture(OUTPUT_TEXTURE);
+        let size = output.size().xy();
+        let bytes_per_pixel = 4 * std::mem::size_of::<f32>() as u32; // Rgba32Float
+        let unpadded_bytes_per_row = size.x * bytes_per_pixel;
+        let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+        let padded_bytes_per_row = unpadded_bytes_per_row.div_ceil(align) * align;
+
+        let readback_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Headless Readback Buffer"),
+            size: (padded_bytes_per_row * size.y) as u64,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Headless Copy Encoder"),
+        });
+        encoder.copy_texture_to_buffer(
+            output.texture().as_image_copy(),
+            wgpu::TexelCopyBufferInfo {
+                buffer: &readback_buffer,
+                layout: wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_bytes_per_row),
+                    rows_per_image: Some(size.y),
+                },
+            },
+            wgpu::Extent3d { width: size.x, height: size.y, depth_or_array_layers: 1 },
+        );
+        queue.submit(Some(encoder.finish()));
+
+        let slice = readback_buffer.slice(..);
+        let (tx, rx) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            tx.send(result).expect("Readback channel closed before result was sent");
+        });
+        queue.on_submitted_work_done(|| {});
+        device.poll(wgpu::PollType::Wait).expect("Failed to poll device");
+        rx.recv().expect("Readback mapping callback never fired").expect("Failed to map readback buffer");
+
+        let mapped = slice.get_mapped_range();
+        let mut pixels = Vec::with_capacity((unpadded_bytes_per_row * size.y) as usize);
+        for row in 0..size.y {
+            let start = (row * padded_bytes_per_row) as usize;
+            let end = start + unpadded_bytes_per_row as usize;
+            pixels.extend_from_slice(&mapped[start..end]);
+        }
+        drop(mapped);
+        readback_buffer.unmap();
+
+        let pixels: &[f32] = bytemuck::cast_slice(&pixels);
+
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("exr") => Self::write_exr(path, size.x, size.y, pixels),
+            _ => Self::write_png(path, size.x, size.y, pixels),
+        }
+    }
+
+    fn write_exr(path: &std::path::Path, width: u32, height: u32, pixels: &[f32]) {
+        use exr::prelude::*;
+        write_rgb_file(path, width as usize, height as usize, |x, y| {
+            let i = (y * width as usize + x) * 4;
+            (pixels[i], pixels[i + 1], pixels[i + 2])
+        }).expect("Failed to write EXR file");
+    }
+
+    fn write_png(path: &std::path::Path, width: u32, height: u32, pixels: &[f32]) {
+        // Match `DisplayPass`'s default Reinhard + sRGB OETF so headless renders look like the live viewport.
+        let oetf = |c: f32| if c < 0.0031308 { 12.92 * c } else { 1.055 * c.powf(1.0 / 2.4) - 0.055 };
+        let tonemap = |c: f32| oetf((c / (1.0 + c)).clamp(0.0, 1.0));
+
+        let mut buffer = image::RgbImage::new(width, height);
+        for (x, y, px) in buffer.enumerate_pixels_mut() {
+            let i = ((y * width + x) * 4) as usize;
+            *px = image::Rgb([
+                (tonemap(pixels[i]) * 255.0).round() as u8,
+                (tonemap(pixels[i + 1]) * 255.0).round() as u8,
+                (tonemap(pixels[i + 2]) * 255.0).round() as u8,
+            ]);
+        }
+        buffer.save(path).expect("Failed to write PNG file");
+    }
+}
+
+impl Pass for Pathtracer {
+    fn record(&self, encoder: &mut wgpu::CommandEncoder, resources: &Resources) {
+        self.record_dispatch(encoder, resources.bind_group(SCENE_BIND_GROUP), resources.texture(OUTPUT_TEXTURE));
+    }
+
+    // `Resources::resize` already reallocated `OUTPUT_TEXTURE` from the factory registered in
+    // `Self::new` by the time this runs, so `global_group` now points at a freed texture's view.
+    // Rebuilding it needs camera/envmap, which aren't owned by `Resources` and so can't be
+    // recovered here - flag it instead, so a missed follow-up `Self::update(..)` call panics in
+    // `record_dispatch` rather than silently rendering into a stale bind group.
+    fn resize(&mut self, _wgpu: &WGPUContext, _resources: &Resources) {
+        self.bind_group_dirty.set(true);
     }
 }
\ No newline at end of file